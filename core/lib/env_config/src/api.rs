@@ -97,7 +97,10 @@ mod tests {
                 pushgateway_url: "http://127.0.0.1:9091".into(),
                 push_interval_ms: Some(100),
             },
-            healthcheck: HealthCheckConfig { port: 8081 },
+            healthcheck: HealthCheckConfig {
+                port: 8081,
+                host: None,
+            },
             merkle_tree: MerkleTreeApiConfig { port: 8082 },
         }
     }
@@ -143,4 +146,23 @@ mod tests {
         let actual = ApiConfig::from_env().unwrap();
         assert_eq!(actual, expected_config());
     }
+
+    #[test]
+    fn healthcheck_from_env_with_host() {
+        let mut lock = MUTEX.lock();
+        let config = r#"
+            API_HEALTHCHECK_PORT=8081
+            API_HEALTHCHECK_HOST=127.0.0.1
+        "#;
+        lock.set_env(config);
+
+        let actual = HealthCheckConfig::from_env().unwrap();
+        assert_eq!(actual.host, Some([127, 0, 0, 1].into()));
+        assert_eq!(actual.bind_addr(), "127.0.0.1:8081".parse().unwrap());
+
+        lock.remove_env(&["API_HEALTHCHECK_HOST"]);
+        let actual = HealthCheckConfig::from_env().unwrap();
+        assert_eq!(actual.host, None);
+        assert_eq!(actual.bind_addr(), "0.0.0.0:8081".parse().unwrap());
+    }
 }
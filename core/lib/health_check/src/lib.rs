@@ -15,6 +15,10 @@ pub enum HealthStatus {
     NotReady,
     /// Component is ready for operations.
     Ready,
+    /// Component is operational, but with reduced capacity (e.g., some of its dependencies are unavailable).
+    Degraded,
+    /// Component is not operational and is about to fail.
+    Critical,
     /// Component is shut down.
     ShutDown,
     /// Component has been abnormally interrupted by a panic.
@@ -23,16 +27,19 @@ pub enum HealthStatus {
 
 impl HealthStatus {
     /// Checks whether a component is ready according to this status.
+    /// Degraded components are considered ready.
     pub fn is_ready(self) -> bool {
-        matches!(self, Self::Ready)
+        matches!(self, Self::Ready | Self::Degraded)
     }
 
     fn priority_for_aggregation(self) -> usize {
         match self {
             Self::Ready => 0,
-            Self::ShutDown => 1,
-            Self::NotReady => 2,
-            Self::Panicked => 3,
+            Self::Degraded => 1,
+            Self::ShutDown => 2,
+            Self::NotReady => 3,
+            Self::Critical => 4,
+            Self::Panicked => 5,
         }
     }
 }
@@ -97,6 +104,11 @@ impl AppHealth {
         Self { inner, components }
     }
 
+    /// Returns the aggregated health status of the application.
+    pub fn status(&self) -> HealthStatus {
+        self.inner.status
+    }
+
     pub fn is_ready(&self) -> bool {
         self.inner.status.is_ready()
     }
@@ -256,4 +268,26 @@ mod tests {
         let updated = health_updater.update(health);
         assert!(updated);
     }
+
+    #[tokio::test]
+    async fn aggregating_health_statuses() {
+        let (first_check, first_updater) = ReactiveHealthCheck::new("first");
+        let (second_check, second_updater) = ReactiveHealthCheck::new("second");
+        let checks: Vec<Box<dyn CheckHealth>> = vec![Box::new(first_check), Box::new(second_check)];
+
+        first_updater.update(HealthStatus::Ready.into());
+        second_updater.update(HealthStatus::Degraded.into());
+        let app_health = AppHealth::new(&checks).await;
+        assert_matches!(app_health.status(), HealthStatus::Degraded);
+        assert!(app_health.is_ready());
+
+        first_updater.update(HealthStatus::Critical.into());
+        let app_health = AppHealth::new(&checks).await;
+        assert_matches!(app_health.status(), HealthStatus::Critical);
+        assert!(!app_health.is_ready());
+
+        drop(second_updater);
+        let app_health = AppHealth::new(&checks).await;
+        assert_matches!(app_health.status(), HealthStatus::Critical);
+    }
 }
@@ -63,3 +63,196 @@ impl GlueFrom<LogQuery> for LogQuery_1_4_1 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use zksync_types::{H160, U256};
+
+    use super::*;
+
+    /// Queries with minimum and maximum values of all fields.
+    fn boundary_queries() -> [LogQuery; 2] {
+        [
+            LogQuery {
+                timestamp: Timestamp(0),
+                tx_number_in_block: 0,
+                aux_byte: 0,
+                shard_id: 0,
+                address: H160::zero(),
+                key: U256::zero(),
+                read_value: U256::zero(),
+                written_value: U256::zero(),
+                rw_flag: false,
+                rollback: false,
+                is_service: false,
+            },
+            LogQuery {
+                timestamp: Timestamp(u32::MAX),
+                tx_number_in_block: u16::MAX,
+                aux_byte: u8::MAX,
+                shard_id: 1,
+                address: H160::repeat_byte(0xff),
+                key: U256::MAX,
+                read_value: U256::from(42),
+                written_value: U256::MAX - 1,
+                rw_flag: true,
+                rollback: true,
+                is_service: true,
+            },
+        ]
+    }
+
+    fn hash_of(query: &LogQuery) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        query.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn log_query_round_trip() {
+        for query in boundary_queries() {
+            let converted: LogQuery_1_4_1 = query.glue_into();
+            assert_eq!(converted.timestamp.0, query.timestamp.0);
+            let round_tripped: LogQuery = converted.glue_into();
+            assert_eq!(round_tripped, query);
+        }
+    }
+
+    /// Deterministically generates queries with pseudo-random contents (using a simple LCG), to complement
+    /// [`boundary_queries()`].
+    fn pseudo_random_queries(count: usize) -> impl Iterator<Item = LogQuery> {
+        let mut state = 0x2545_f491_4f6c_dd1d_u64;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            state
+        };
+        (0..count).map(move |_| LogQuery {
+            timestamp: Timestamp(next() as u32),
+            tx_number_in_block: next() as u16,
+            aux_byte: next() as u8,
+            shard_id: next() as u8,
+            address: H160::from_low_u64_be(next()),
+            key: U256([next(), next(), next(), next()]),
+            read_value: U256([next(), next(), next(), next()]),
+            written_value: U256([next(), next(), next(), next()]),
+            rw_flag: next() % 2 == 0,
+            rollback: next() % 2 == 0,
+            is_service: next() % 2 == 0,
+        })
+    }
+
+    /// Returns copies of `query`, each with exactly one field changed.
+    fn single_field_perturbations(query: LogQuery) -> [(&'static str, LogQuery); 11] {
+        let one = U256::one();
+        let mut address = query.address;
+        address.0[0] ^= 1;
+        [
+            (
+                "timestamp",
+                LogQuery {
+                    timestamp: Timestamp(query.timestamp.0.wrapping_add(1)),
+                    ..query
+                },
+            ),
+            (
+                "tx_number_in_block",
+                LogQuery {
+                    tx_number_in_block: query.tx_number_in_block.wrapping_add(1),
+                    ..query
+                },
+            ),
+            (
+                "aux_byte",
+                LogQuery {
+                    aux_byte: query.aux_byte.wrapping_add(1),
+                    ..query
+                },
+            ),
+            (
+                "shard_id",
+                LogQuery {
+                    shard_id: query.shard_id.wrapping_add(1),
+                    ..query
+                },
+            ),
+            ("address", LogQuery { address, ..query }),
+            (
+                "key",
+                LogQuery {
+                    key: query.key.overflowing_add(one).0,
+                    ..query
+                },
+            ),
+            (
+                "read_value",
+                LogQuery {
+                    read_value: query.read_value.overflowing_add(one).0,
+                    ..query
+                },
+            ),
+            (
+                "written_value",
+                LogQuery {
+                    written_value: query.written_value.overflowing_add(one).0,
+                    ..query
+                },
+            ),
+            (
+                "rw_flag",
+                LogQuery {
+                    rw_flag: !query.rw_flag,
+                    ..query
+                },
+            ),
+            (
+                "rollback",
+                LogQuery {
+                    rollback: !query.rollback,
+                    ..query
+                },
+            ),
+            (
+                "is_service",
+                LogQuery {
+                    is_service: !query.is_service,
+                    ..query
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn log_query_hash_is_consistent_with_eq() {
+        let queries = boundary_queries()
+            .into_iter()
+            .chain(pseudo_random_queries(100));
+        for query in queries {
+            let copy = query;
+            assert_eq!(copy, query);
+            assert_eq!(hash_of(&copy), hash_of(&query));
+
+            let round_tripped: LogQuery = GlueInto::<LogQuery_1_4_1>::glue_into(query).glue_into();
+            assert_eq!(round_tripped, query);
+            assert_eq!(hash_of(&round_tripped), hash_of(&query));
+
+            for (field, modified) in single_field_perturbations(query) {
+                assert_ne!(
+                    modified, query,
+                    "changing `{field}` did not affect equality"
+                );
+                assert_ne!(
+                    hash_of(&modified),
+                    hash_of(&query),
+                    "changing `{field}` did not affect hash"
+                );
+            }
+        }
+    }
+}
@@ -43,6 +43,7 @@ pub mod fee_model;
 pub mod l1;
 pub mod l2;
 pub mod l2_to_l1_log;
+pub mod log_query;
 pub mod priority_op_onchain_data;
 pub mod protocol_version;
 pub mod snapshots;
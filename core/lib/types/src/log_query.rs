@@ -0,0 +1,669 @@
+//! Helpers for processing [`LogQuery`] sequences produced by the VM.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    io::{self, BufRead, BufReader},
+    ops::RangeInclusive,
+    str::FromStr,
+};
+
+use zk_evm::aux_structures::{LogQuery, Timestamp};
+use zksync_utils::u256_to_h256;
+
+use crate::{web3::signing::keccak256, AccountTreeId, Address, StorageKey, U256};
+
+/// Predicate for [`filter()`]. Conditions that are set to `None` match all queries.
+#[derive(Debug, Clone, Default)]
+pub struct LogQueryPredicate {
+    /// Addresses of contracts to select queries for.
+    pub addresses: Option<HashSet<Address>>,
+    /// Range of storage keys to select queries for.
+    pub key_range: Option<RangeInclusive<U256>>,
+    /// If set, selects only writes (`true`) or only reads (`false`).
+    pub rw_flag: Option<bool>,
+}
+
+impl LogQueryPredicate {
+    /// Checks whether the provided query satisfies this predicate.
+    pub fn matches(&self, query: &LogQuery) -> bool {
+        let address_matches = self
+            .addresses
+            .as_ref()
+            .map_or(true, |addresses| addresses.contains(&query.address));
+        let key_matches = self
+            .key_range
+            .as_ref()
+            .map_or(true, |range| range.contains(&query.key));
+        let rw_flag_matches = self.rw_flag.map_or(true, |flag| flag == query.rw_flag);
+        address_matches && key_matches && rw_flag_matches
+    }
+}
+
+/// Selects queries matching the provided predicate, preserving their order.
+pub fn filter<'a>(
+    queries: &'a [LogQuery],
+    predicate: LogQueryPredicate,
+) -> impl Iterator<Item = &'a LogQuery> + 'a {
+    queries.iter().filter(move |query| predicate.matches(query))
+}
+
+/// Returns the storage key written by the query, or `None` if the query is not a storage write.
+pub fn to_storage_key(query: &LogQuery) -> Option<StorageKey> {
+    if !query.rw_flag || query.is_service {
+        return None;
+    }
+    let address = AccountTreeId::new(query.address);
+    Some(StorageKey::new(address, u256_to_h256(query.key)))
+}
+
+/// Computes the net effect of a storage write and its rollback.
+///
+/// The returned query is `write` with `written_value` reset to `write.read_value`, i.e. a write
+/// that doesn't change the slot. Returns an error if `rollback` isn't a rollback of `write`.
+pub fn apply_rollback(write: &LogQuery, rollback: &LogQuery) -> anyhow::Result<LogQuery> {
+    anyhow::ensure!(
+        write.rw_flag && !write.rollback,
+        "Query {write:?} is not a non-rolled back write"
+    );
+    anyhow::ensure!(
+        rollback.rw_flag && rollback.rollback,
+        "Query {rollback:?} is not a rollback of a write"
+    );
+    anyhow::ensure!(
+        write.shard_id == rollback.shard_id
+            && write.address == rollback.address
+            && write.key == rollback.key,
+        "Rollback {rollback:?} targets a different storage slot than write {write:?}"
+    );
+
+    Ok(LogQuery {
+        written_value: write.read_value,
+        ..*write
+    })
+}
+
+/// Removes writes that were rolled back together with their rollbacks, preserving the order of remaining queries.
+///
+/// A rollback cancels the latest preceding write to the same storage slot with the same timestamp. Rollbacks
+/// without a matching write (e.g., if the write is not a part of `queries`) are retained.
+pub fn deduplicate(queries: impl IntoIterator<Item = LogQuery>) -> Vec<LogQuery> {
+    let mut output = vec![];
+    // Indices of not rolled back writes in `output` for each storage slot.
+    let mut pending_writes = HashMap::<_, Vec<usize>>::new();
+
+    for query in queries {
+        if !query.rw_flag {
+            output.push(Some(query));
+            continue;
+        }
+
+        let slot = (query.shard_id, query.address, query.key);
+        let slot_writes = pending_writes.entry(slot).or_default();
+        if query.rollback {
+            let matching_write_position = slot_writes.iter().rposition(|&idx| {
+                output[idx].map_or(false, |write: LogQuery| write.timestamp == query.timestamp)
+            });
+            if let Some(position) = matching_write_position {
+                let write_idx = slot_writes.remove(position);
+                output[write_idx] = None;
+                continue;
+            }
+        } else {
+            slot_writes.push(output.len());
+        }
+        output.push(Some(query));
+    }
+    output.into_iter().flatten().collect()
+}
+
+/// Side of a [`diff()`] that a query belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    A,
+    B,
+}
+
+/// Difference between two sequences of log queries at a certain position, as returned by [`diff()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LogQueryDiff {
+    /// Query is present only in one of the sequences (i.e., the other sequence is shorter).
+    Only(Side, LogQuery),
+    /// Sequences have different queries at the same position.
+    Differs { a: LogQuery, b: LogQuery },
+}
+
+/// Formats the diff in the unified diff style: queries from `a` are prefixed with `-`, and ones from `b` with `+`.
+impl fmt::Display for LogQueryDiff {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Only(Side::A, query) => write_diff_line(formatter, '-', query),
+            Self::Only(Side::B, query) => write_diff_line(formatter, '+', query),
+            Self::Differs { a, b } => {
+                write_diff_line(formatter, '-', a)?;
+                writeln!(formatter)?;
+                write_diff_line(formatter, '+', b)
+            }
+        }
+    }
+}
+
+fn write_diff_line(
+    formatter: &mut fmt::Formatter<'_>,
+    prefix: char,
+    query: &LogQuery,
+) -> fmt::Result {
+    let kind = if query.rw_flag { "write" } else { "read" };
+    write!(
+        formatter,
+        "{prefix} ts={} tx={} {kind} {:?}[{:#x}] {:#x} -> {:#x}",
+        query.timestamp.0,
+        query.tx_number_in_block,
+        query.address,
+        query.key,
+        query.read_value,
+        query.written_value
+    )?;
+    if query.rollback {
+        write!(formatter, " (rollback)")?;
+    }
+    if query.is_service {
+        write!(formatter, " (service)")?;
+    }
+    Ok(())
+}
+
+/// Compares two log query sequences (e.g., produced by different nodes executing the same transaction) position-wise.
+/// Positions with equal queries are omitted from the output.
+pub fn diff(a: &[LogQuery], b: &[LogQuery]) -> Vec<LogQueryDiff> {
+    let common_len = a.len().min(b.len());
+    let differing = a
+        .iter()
+        .zip(b)
+        .filter(|(a, b)| a != b)
+        .map(|(&a, &b)| LogQueryDiff::Differs { a, b });
+    let only_a = a[common_len..]
+        .iter()
+        .map(|&query| LogQueryDiff::Only(Side::A, query));
+    let only_b = b[common_len..]
+        .iter()
+        .map(|&query| LogQueryDiff::Only(Side::B, query));
+    differing.chain(only_a).chain(only_b).collect()
+}
+
+/// Bloom filter over `(address, key)` pairs touched by a batch of [`LogQuery`]s.
+///
+/// [`Self::may_contain()`] never returns `false` for a pair present in the batch; it may return `true`
+/// for an absent pair with probability close to [`Self::TARGET_FALSE_POSITIVE_RATE`].
+#[derive(Debug, Clone)]
+pub struct LogQueryBloomFilter {
+    bits: Vec<u64>,
+    hash_count: u32,
+}
+
+impl LogQueryBloomFilter {
+    /// False positive rate the filter is sized for.
+    pub const TARGET_FALSE_POSITIVE_RATE: f64 = 0.01;
+
+    /// Creates a filter containing `(address, key)` pairs of all provided queries.
+    pub fn from_queries(queries: &[LogQuery]) -> Self {
+        let item_count = queries.len().max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        // Optimal bit and hash function counts for the target false positive rate.
+        let bit_count = (-item_count * Self::TARGET_FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil();
+        let bit_count = (bit_count as usize).max(64);
+        let hash_count = ((bit_count as f64 / item_count) * ln2).round().max(1.0) as u32;
+
+        let mut this = Self {
+            bits: vec![0; (bit_count + 63) / 64],
+            hash_count,
+        };
+        for query in queries {
+            for bit in this.bit_indices(query.address, query.key) {
+                this.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        this
+    }
+
+    /// Checks whether the `(address, key)` pair may be present in the batch.
+    pub fn may_contain(&self, address: Address, key: U256) -> bool {
+        self.bit_indices(address, key)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// Derives bit indices for a pair using double hashing over a single keccak256 digest.
+    fn bit_indices(&self, address: Address, key: U256) -> impl Iterator<Item = usize> {
+        let mut preimage = [0_u8; 52];
+        preimage[..20].copy_from_slice(address.as_bytes());
+        key.to_big_endian(&mut preimage[20..]);
+        let digest = keccak256(&preimage);
+        let h1 = u64::from_be_bytes(digest[..8].try_into().unwrap());
+        let h2 = u64::from_be_bytes(digest[8..16].try_into().unwrap()) | 1;
+
+        let bit_count = (self.bits.len() * 64) as u64;
+        (0..u64::from(self.hash_count))
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % bit_count) as usize)
+    }
+}
+
+/// Header of the CSV format used by [`write_csv()`] and [`read_csv()`]. Columns follow the order of [`LogQuery`] fields.
+const CSV_HEADER: &str =
+    "timestamp,tx_number_in_block,aux_byte,shard_id,address,key,read_value,written_value,\
+    rw_flag,rollback,is_service";
+
+/// Error reading log queries in the CSV format.
+#[derive(Debug, thiserror::Error)]
+pub enum CsvError {
+    #[error("I/O error reading CSV: {0}")]
+    Io(#[from] io::Error),
+    #[error("unexpected CSV header: {0:?}")]
+    InvalidHeader(String),
+    #[error("invalid CSV record on line {line}: {message}")]
+    InvalidRecord { line: usize, message: String },
+}
+
+/// Writes log queries in the CSV format (with a header) for debugging. Address is hex-encoded; other numeric fields
+/// are encoded as decimals.
+pub fn write_csv<W: io::Write>(queries: &[LogQuery], mut writer: W) -> io::Result<()> {
+    writeln!(writer, "{CSV_HEADER}")?;
+    for query in queries {
+        writeln!(
+            writer,
+            "{},{},{},{},{:?},{},{},{},{},{},{}",
+            query.timestamp.0,
+            query.tx_number_in_block,
+            query.aux_byte,
+            query.shard_id,
+            query.address,
+            query.key,
+            query.read_value,
+            query.written_value,
+            query.rw_flag,
+            query.rollback,
+            query.is_service
+        )?;
+    }
+    writer.flush()
+}
+
+/// Reads log queries written by [`write_csv()`].
+pub fn read_csv<R: io::Read>(reader: R) -> Result<Vec<LogQuery>, CsvError> {
+    let mut lines = BufReader::new(reader).lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    if header != CSV_HEADER {
+        return Err(CsvError::InvalidHeader(header));
+    }
+
+    let mut queries = vec![];
+    for (i, line) in lines.enumerate() {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+        // Account for the header and 1-based line numbering.
+        let line_number = i + 2;
+        let query = parse_csv_record(&line).map_err(|message| CsvError::InvalidRecord {
+            line: line_number,
+            message,
+        })?;
+        queries.push(query);
+    }
+    Ok(queries)
+}
+
+fn parse_csv_record(record: &str) -> Result<LogQuery, String> {
+    fn parse<T: FromStr>(field: Option<&str>, name: &str) -> Result<T, String> {
+        let field = field.ok_or_else(|| format!("missing `{name}`"))?;
+        field
+            .parse()
+            .map_err(|_| format!("cannot parse `{name}` from {field:?}"))
+    }
+
+    fn parse_u256(field: Option<&str>, name: &str) -> Result<U256, String> {
+        let field = field.ok_or_else(|| format!("missing `{name}`"))?;
+        U256::from_dec_str(field)
+            .map_err(|err| format!("cannot parse `{name}` from {field:?}: {err:?}"))
+    }
+
+    let mut fields = record.split(',');
+    let query = LogQuery {
+        timestamp: Timestamp(parse(fields.next(), "timestamp")?),
+        tx_number_in_block: parse(fields.next(), "tx_number_in_block")?,
+        aux_byte: parse(fields.next(), "aux_byte")?,
+        shard_id: parse(fields.next(), "shard_id")?,
+        address: parse(fields.next(), "address")?,
+        key: parse_u256(fields.next(), "key")?,
+        read_value: parse_u256(fields.next(), "read_value")?,
+        written_value: parse_u256(fields.next(), "written_value")?,
+        rw_flag: parse(fields.next(), "rw_flag")?,
+        rollback: parse(fields.next(), "rollback")?,
+        is_service: parse(fields.next(), "is_service")?,
+    };
+    if fields.next().is_some() {
+        return Err("too many fields".to_owned());
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::H160;
+
+    fn write_query(address: H160, key: U256, read_value: U256, written_value: U256) -> LogQuery {
+        LogQuery {
+            timestamp: Timestamp(0),
+            tx_number_in_block: 0,
+            aux_byte: 0,
+            shard_id: 0,
+            address,
+            key,
+            read_value,
+            written_value,
+            rw_flag: true,
+            rollback: false,
+            is_service: false,
+        }
+    }
+
+    #[test]
+    fn converting_query_to_storage_key() {
+        let address = H160::repeat_byte(1);
+        let write = write_query(address, 2.into(), 0.into(), 1.into());
+        let key = to_storage_key(&write).unwrap();
+        assert_eq!(*key.address(), address);
+        assert_eq!(*key.key(), u256_to_h256(2.into()));
+
+        let read = LogQuery {
+            rw_flag: false,
+            ..write
+        };
+        assert_eq!(to_storage_key(&read), None);
+        let service_write = LogQuery {
+            is_service: true,
+            ..write
+        };
+        assert_eq!(to_storage_key(&service_write), None);
+    }
+
+    #[test]
+    fn applying_rollback() {
+        let write = write_query(H160::repeat_byte(1), 2.into(), 3.into(), 4.into());
+        let rollback = LogQuery {
+            rollback: true,
+            ..write
+        };
+
+        let net_query = apply_rollback(&write, &rollback).unwrap();
+        assert_eq!(net_query.written_value, U256::from(3));
+        assert_eq!(net_query.read_value, U256::from(3));
+        assert!(!net_query.rollback);
+    }
+
+    #[test]
+    fn applying_invalid_rollback() {
+        let write = write_query(H160::repeat_byte(1), 2.into(), 3.into(), 4.into());
+        apply_rollback(&write, &write).unwrap_err();
+
+        let rollback = LogQuery {
+            rollback: true,
+            ..write
+        };
+        apply_rollback(&rollback, &rollback).unwrap_err();
+
+        let other_slot_rollback = LogQuery {
+            key: 5.into(),
+            ..rollback
+        };
+        apply_rollback(&write, &other_slot_rollback).unwrap_err();
+
+        let read = LogQuery {
+            rw_flag: false,
+            ..write
+        };
+        apply_rollback(&read, &rollback).unwrap_err();
+    }
+
+    #[test]
+    fn filtering_queries() {
+        let first_address = H160::repeat_byte(1);
+        let second_address = H160::repeat_byte(2);
+        let queries = [
+            write_query(first_address, 1.into(), 0.into(), 1.into()),
+            write_query(first_address, 10.into(), 0.into(), 1.into()),
+            write_query(second_address, 1.into(), 0.into(), 1.into()),
+            LogQuery {
+                rw_flag: false,
+                ..write_query(first_address, 2.into(), 0.into(), 0.into())
+            },
+        ];
+
+        let all: Vec<_> = filter(&queries, LogQueryPredicate::default()).collect();
+        assert_eq!(all.len(), queries.len());
+
+        let predicate = LogQueryPredicate {
+            addresses: Some(HashSet::from([first_address])),
+            ..LogQueryPredicate::default()
+        };
+        let by_address: Vec<_> = filter(&queries, predicate).collect();
+        assert_eq!(by_address, [&queries[0], &queries[1], &queries[3]]);
+
+        let predicate = LogQueryPredicate {
+            addresses: Some(HashSet::from([first_address])),
+            key_range: Some(U256::from(1)..=U256::from(5)),
+            rw_flag: None,
+        };
+        let by_key: Vec<_> = filter(&queries, predicate).collect();
+        assert_eq!(by_key, [&queries[0], &queries[3]]);
+
+        let predicate = LogQueryPredicate {
+            rw_flag: Some(false),
+            ..LogQueryPredicate::default()
+        };
+        let reads: Vec<_> = filter(&queries, predicate).collect();
+        assert_eq!(reads, [&queries[3]]);
+    }
+
+    #[test]
+    fn deduplicating_rolled_back_writes() {
+        let address = H160::repeat_byte(1);
+        let first_write = write_query(address, 1.into(), 0.into(), 1.into());
+        let other_slot_write = LogQuery {
+            timestamp: Timestamp(1),
+            ..write_query(address, 2.into(), 0.into(), 1.into())
+        };
+        let second_write = LogQuery {
+            timestamp: Timestamp(2),
+            ..write_query(address, 1.into(), 1.into(), 2.into())
+        };
+        let read = LogQuery {
+            timestamp: Timestamp(3),
+            rw_flag: false,
+            ..write_query(address, 1.into(), 2.into(), 2.into())
+        };
+        let second_rollback = LogQuery {
+            rollback: true,
+            ..second_write
+        };
+        let queries = [
+            first_write,
+            other_slot_write,
+            second_write,
+            read,
+            second_rollback,
+        ];
+
+        let deduplicated = deduplicate(queries);
+        assert_eq!(deduplicated, [first_write, other_slot_write, read]);
+    }
+
+    #[test]
+    fn deduplicating_cross_transaction_rollbacks() {
+        let address = H160::repeat_byte(1);
+        let first_tx_write = write_query(address, 1.into(), 0.into(), 1.into());
+        let second_tx_write = LogQuery {
+            timestamp: Timestamp(1),
+            tx_number_in_block: 1,
+            ..write_query(address, 1.into(), 1.into(), 2.into())
+        };
+        let third_tx_write = LogQuery {
+            timestamp: Timestamp(2),
+            tx_number_in_block: 2,
+            ..write_query(address, 1.into(), 2.into(), 3.into())
+        };
+
+        // The second transaction is reverted; the writes of other transactions must be retained.
+        let queries = [
+            first_tx_write,
+            second_tx_write,
+            LogQuery {
+                rollback: true,
+                ..second_tx_write
+            },
+            third_tx_write,
+        ];
+        let deduplicated = deduplicate(queries);
+        assert_eq!(deduplicated, [first_tx_write, third_tx_write]);
+
+        // A rollback without a matching write is retained.
+        let orphan_rollback = LogQuery {
+            timestamp: Timestamp(5),
+            rollback: true,
+            ..first_tx_write
+        };
+        let deduplicated = deduplicate([first_tx_write, orphan_rollback]);
+        assert_eq!(deduplicated, [first_tx_write, orphan_rollback]);
+    }
+
+    #[test]
+    fn diffing_queries() {
+        let address = H160::repeat_byte(1);
+        let queries: Vec<_> = (0_u64..3)
+            .map(|i| write_query(address, i.into(), 0.into(), 1.into()))
+            .collect();
+        assert!(diff(&queries, &queries).is_empty());
+
+        let mut other_queries = queries.clone();
+        other_queries[1].written_value = 2.into();
+        other_queries.push(write_query(address, 3.into(), 0.into(), 1.into()));
+        let diffs = diff(&queries, &other_queries);
+        assert_eq!(
+            diffs,
+            [
+                LogQueryDiff::Differs {
+                    a: queries[1],
+                    b: other_queries[1],
+                },
+                LogQueryDiff::Only(Side::B, other_queries[3]),
+            ]
+        );
+
+        let diffs = diff(&other_queries, &queries[..1]);
+        assert_eq!(diffs.len(), 3);
+        assert!(diffs
+            .iter()
+            .all(|diff| matches!(diff, LogQueryDiff::Only(Side::A, _))));
+    }
+
+    #[test]
+    fn displaying_diff() {
+        let a = write_query(H160::repeat_byte(1), 2.into(), 0.into(), 1.into());
+        let b = LogQuery {
+            written_value: 3.into(),
+            rollback: true,
+            ..a
+        };
+        let diff = LogQueryDiff::Differs { a, b };
+        let address = format!("{:?}", H160::repeat_byte(1));
+        assert_eq!(
+            diff.to_string(),
+            format!(
+                "- ts=0 tx=0 write {address}[0x2] 0x0 -> 0x1\n\
+                 + ts=0 tx=0 write {address}[0x2] 0x0 -> 0x3 (rollback)"
+            )
+        );
+        let diff = LogQueryDiff::Only(Side::B, a);
+        assert!(diff.to_string().starts_with("+ "), "{diff}");
+    }
+
+    #[test]
+    fn bloom_filter_contains_all_queries() {
+        let queries: Vec<_> = (0_u64..1_000)
+            .map(|i| {
+                write_query(
+                    H160::repeat_byte((i % 7) as u8),
+                    i.into(),
+                    0.into(),
+                    1.into(),
+                )
+            })
+            .collect();
+        let filter = LogQueryBloomFilter::from_queries(&queries);
+        for query in &queries {
+            assert!(filter.may_contain(query.address, query.key));
+        }
+
+        let empty_filter = LogQueryBloomFilter::from_queries(&[]);
+        assert!(!empty_filter.may_contain(H160::zero(), U256::zero()));
+    }
+
+    #[test]
+    fn bloom_filter_false_positive_rate() {
+        let address = H160::repeat_byte(1);
+        let queries: Vec<_> = (0_u64..1_000)
+            .map(|i| write_query(address, i.into(), 0.into(), 1.into()))
+            .collect();
+        let filter = LogQueryBloomFilter::from_queries(&queries);
+
+        let probe_count = 100_000_u64;
+        let false_positives = (1_000..1_000 + probe_count)
+            .filter(|&i| filter.may_contain(address, i.into()))
+            .count();
+        let false_positive_rate = false_positives as f64 / probe_count as f64;
+        // Allow for statistical deviation from the target rate.
+        assert!(
+            false_positive_rate < LogQueryBloomFilter::TARGET_FALSE_POSITIVE_RATE * 2.0,
+            "false positive rate {false_positive_rate} is too high"
+        );
+    }
+
+    #[test]
+    fn csv_round_trip() {
+        let queries = [
+            write_query(H160::repeat_byte(0xab), 1.into(), 0.into(), U256::MAX),
+            LogQuery {
+                timestamp: Timestamp(u32::MAX),
+                tx_number_in_block: 3,
+                aux_byte: 1,
+                shard_id: 1,
+                rw_flag: false,
+                rollback: true,
+                is_service: true,
+                ..write_query(H160::zero(), U256::MAX, 42.into(), 0.into())
+            },
+        ];
+
+        let mut buffer = vec![];
+        write_csv(&queries, &mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        assert_eq!(csv.lines().count(), queries.len() + 1);
+        assert!(csv.starts_with(CSV_HEADER), "{csv}");
+
+        let restored_queries = read_csv(csv.as_bytes()).unwrap();
+        assert_eq!(restored_queries, queries);
+    }
+
+    #[test]
+    fn reading_invalid_csv() {
+        let err = read_csv("timestamp,key\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, CsvError::InvalidHeader(_)), "{err}");
+
+        let csv = format!("{CSV_HEADER}\n0,0,0,0,0x0000000000000000000000000000000000000000,1,2,3,yes,false,false\n");
+        let err = read_csv(csv.as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, CsvError::InvalidRecord { line: 2, .. }),
+            "{err}"
+        );
+    }
+}
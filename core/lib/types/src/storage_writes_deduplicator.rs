@@ -1,10 +1,8 @@
 use std::collections::HashMap;
 
-use zksync_utils::u256_to_h256;
-
 use crate::{
-    tx::tx_execution_info::DeduplicatedWritesMetrics,
-    writes::compression::compress_with_best_strategy, AccountTreeId, StorageKey, StorageLogQuery,
+    log_query::to_storage_key, tx::tx_execution_info::DeduplicatedWritesMetrics,
+    writes::compression::compress_with_best_strategy, StorageKey, StorageLogQuery,
     StorageLogQueryType, U256,
 };
 
@@ -88,11 +86,12 @@ impl StorageWritesDeduplicator {
         logs: I,
     ) -> Vec<UpdateItem> {
         let mut updates = Vec::new();
-        for log in logs.into_iter().filter(|log| log.log_query.rw_flag) {
-            let key = StorageKey::new(
-                AccountTreeId::new(log.log_query.address),
-                u256_to_h256(log.log_query.key),
-            );
+        // Storage logs are never service queries (`is_service` is only set for L2-to-L1 logs), so this selects
+        // all storage writes.
+        let writes = logs
+            .into_iter()
+            .filter_map(|log| Some((log, to_storage_key(&log.log_query)?)));
+        for (log, key) in writes {
             let initial_value = *self
                 .initial_values
                 .entry(key)
@@ -220,9 +219,10 @@ impl StorageWritesDeduplicator {
 #[cfg(test)]
 mod tests {
     use zk_evm::aux_structures::{LogQuery, Timestamp};
+    use zksync_utils::u256_to_h256;
 
     use super::*;
-    use crate::H160;
+    use crate::{AccountTreeId, H160};
 
     fn storage_log_query(
         key: U256,
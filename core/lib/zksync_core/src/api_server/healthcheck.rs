@@ -1,19 +1,33 @@
 use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::Duration};
 
-use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    routing::get,
+    Json, Router,
+};
 use tokio::sync::watch;
-use zksync_health_check::{AppHealth, CheckHealth};
+use zksync_health_check::{AppHealth, CheckHealth, HealthStatus};
 
 type SharedHealthchecks = Arc<[Box<dyn CheckHealth>]>;
 
-async fn check_health(health_checks: State<SharedHealthchecks>) -> (StatusCode, Json<AppHealth>) {
+async fn check_health(
+    health_checks: State<SharedHealthchecks>,
+) -> (StatusCode, HeaderMap, Json<AppHealth>) {
     let response = AppHealth::new(&health_checks).await;
     let response_code = if response.is_ready() {
         StatusCode::OK
     } else {
         StatusCode::SERVICE_UNAVAILABLE
     };
-    (response_code, Json(response))
+    let mut headers = HeaderMap::new();
+    if response.status() == HealthStatus::Degraded {
+        headers.insert(
+            header::WARNING,
+            HeaderValue::from_static("199 - \"Application is degraded\""),
+        );
+    }
+    (response_code, headers, Json(response))
 }
 
 async fn run_server(
@@ -87,3 +101,36 @@ impl HealthCheckHandle {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use zksync_health_check::ReactiveHealthCheck;
+
+    use super::*;
+
+    async fn check_health_with_status(status: HealthStatus) -> (StatusCode, Option<HeaderValue>) {
+        let (health_check, health_updater) = ReactiveHealthCheck::new("test");
+        health_updater.update(status.into());
+        let health_checks: Vec<Box<dyn CheckHealth>> = vec![Box::new(health_check)];
+
+        let (status_code, headers, _) =
+            check_health(State(SharedHealthchecks::from(health_checks))).await;
+        (status_code, headers.get(header::WARNING).cloned())
+    }
+
+    #[tokio::test]
+    async fn health_status_response_codes() {
+        let (status_code, warning) = check_health_with_status(HealthStatus::Ready).await;
+        assert_eq!(status_code, StatusCode::OK);
+        assert_eq!(warning, None);
+
+        let (status_code, warning) = check_health_with_status(HealthStatus::Degraded).await;
+        assert_eq!(status_code, StatusCode::OK);
+        let warning = warning.expect("no warning header for degraded app");
+        assert_eq!(warning, "199 - \"Application is degraded\"");
+
+        let (status_code, warning) = check_health_with_status(HealthStatus::Critical).await;
+        assert_eq!(status_code, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(warning, None);
+    }
+}
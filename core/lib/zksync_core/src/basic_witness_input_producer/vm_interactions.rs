@@ -57,6 +57,7 @@ pub(super) fn create_vm(
             validation_computational_gas_limit,
             l2_chain_id,
         ))
+        .context("failed loading L1 batch params")?
         .context("expected miniblock to be executed and sealed")?;
 
     let pg_storage = PostgresStorage::new(rt_handle.clone(), connection, miniblock_number, true);
@@ -1,7 +1,10 @@
 use assert_matches::assert_matches;
 use zksync_dal::ConnectionPool;
 use zksync_test_account::Account;
-use zksync_types::PriorityOpId;
+use zksync_types::{
+    fee_model::{BatchFeeInput, PubdataIndependentBatchFeeModelInput},
+    PriorityOpId, SYSTEM_CONTEXT_MINIMAL_BASE_FEE,
+};
 
 use self::tester::Tester;
 use super::TxExecutionResult;
@@ -143,6 +146,64 @@ async fn reject_tx() {
     assert_rejected(&res);
 }
 
+/// Fee input for which the base fee derived by the VM is `SYSTEM_CONTEXT_MINIMAL_BASE_FEE`, i.e. the max fee per gas
+/// of transactions produced by the tester.
+fn minimal_base_fee_input() -> BatchFeeInput {
+    BatchFeeInput::PubdataIndependent(PubdataIndependentBatchFeeModelInput {
+        fair_l2_gas_price: SYSTEM_CONTEXT_MINIMAL_BASE_FEE,
+        fair_pubdata_price: 1,
+        l1_gas_price: 1,
+    })
+}
+
+/// Checks that the VM accepts the base fee enforced via `l1_batch_params_with_enforced_base_fee()`.
+#[tokio::test]
+async fn execute_tx_with_enforced_base_fee() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut alice = Account::random();
+
+    let tester = Tester::new(connection_pool);
+    tester.genesis().await;
+    tester.fund(&[alice.address()]).await;
+    let (system_env, l1_batch_env) = tester.batch_params_with_enforced_base_fee(
+        minimal_base_fee_input(),
+        SYSTEM_CONTEXT_MINIMAL_BASE_FEE,
+    );
+    assert_eq!(
+        l1_batch_env.enforced_base_fee,
+        Some(SYSTEM_CONTEXT_MINIMAL_BASE_FEE)
+    );
+    let executor = tester
+        .create_batch_executor_with_params(l1_batch_env, system_env)
+        .await;
+
+    let res = executor.execute_tx(alice.execute()).await;
+    assert_executed(&res);
+    executor.finish_batch().await;
+}
+
+/// Checks that the enforced base fee reaches the VM: the bootloader compares it with the base fee
+/// derived from the fee input, so a mismatching value must make the VM reject transactions.
+#[tokio::test]
+async fn enforced_base_fee_is_passed_to_vm() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut alice = Account::random();
+
+    let tester = Tester::new(connection_pool);
+    tester.genesis().await;
+    tester.fund(&[alice.address()]).await;
+    let (system_env, l1_batch_env) = tester.batch_params_with_enforced_base_fee(
+        minimal_base_fee_input(),
+        SYSTEM_CONTEXT_MINIMAL_BASE_FEE + 1,
+    );
+    let executor = tester
+        .create_batch_executor_with_params(l1_batch_env, system_env)
+        .await;
+
+    let res = executor.execute_tx(alice.execute()).await;
+    assert_rejected(&res);
+}
+
 /// Checks that tx with too big gas limit is correctly rejected.
 #[tokio::test]
 async fn too_big_gas_limit() {
@@ -12,10 +12,11 @@ use zksync_dal::ConnectionPool;
 use zksync_state::RocksdbStorage;
 use zksync_test_account::{Account, DeployContractsTx, TxType};
 use zksync_types::{
-    ethabi::Token, fee::Fee, system_contracts::get_system_smart_contracts,
-    utils::storage_key_for_standard_token_balance, AccountTreeId, Address, Execute, L1BatchNumber,
-    L2ChainId, MiniblockNumber, PriorityOpId, ProtocolVersionId, StorageLog, Transaction, H256,
-    L2_ETH_TOKEN_ADDRESS, SYSTEM_CONTEXT_MINIMAL_BASE_FEE, U256,
+    block::MiniblockHasher, ethabi::Token, fee::Fee, fee_model::BatchFeeInput,
+    system_contracts::get_system_smart_contracts, utils::storage_key_for_standard_token_balance,
+    AccountTreeId, Address, Execute, L1BatchNumber, L2ChainId, MiniblockNumber, PriorityOpId,
+    ProtocolVersionId, StorageLog, Transaction, H256, L2_ETH_TOKEN_ADDRESS,
+    SYSTEM_CONTEXT_MINIMAL_BASE_FEE, U256,
 };
 use zksync_utils::u256_to_h256;
 
@@ -23,6 +24,7 @@ use crate::{
     genesis::create_genesis_l1_batch,
     state_keeper::{
         batch_executor::BatchExecutorHandle,
+        io::common::l1_batch_params_with_enforced_base_fee,
         tests::{default_l1_batch_env, default_system_env, BASE_SYSTEM_CONTRACTS},
     },
 };
@@ -92,7 +94,16 @@ impl Tester {
             100,
             self.config.validation_computational_gas_limit,
         );
+        self.create_batch_executor_with_params(l1_batch, system_env)
+            .await
+    }
 
+    /// Creates a batch executor instance for the first L1 batch with the provided batch params.
+    pub(super) async fn create_batch_executor_with_params(
+        &self,
+        l1_batch: L1BatchEnv,
+        system_env: SystemEnv,
+    ) -> BatchExecutorHandle {
         let mut secondary_storage = RocksdbStorage::new(self.db_dir.path());
         let mut conn = self
             .pool
@@ -134,6 +145,30 @@ impl Tester {
         (batch_params, system_params)
     }
 
+    /// Creates params for the first L1 batch using the provided fee input and enforced base fee, as the state keeper
+    /// would when starting a batch.
+    pub(super) fn batch_params_with_enforced_base_fee(
+        &self,
+        fee_input: BatchFeeInput,
+        enforced_base_fee: u64,
+    ) -> (SystemEnv, L1BatchEnv) {
+        l1_batch_params_with_enforced_base_fee(
+            Some(enforced_base_fee),
+            L1BatchNumber(1),
+            self.fee_account,
+            100,
+            U256::zero(),
+            fee_input,
+            MiniblockNumber(1),
+            MiniblockHasher::legacy_hash(MiniblockNumber(0)),
+            BASE_SYSTEM_CONTRACTS.clone(),
+            self.config.validation_computational_gas_limit,
+            ProtocolVersionId::latest(),
+            1,
+            L2ChainId::from(CHAIN_ID),
+        )
+    }
+
     /// Performs the genesis in the storage.
     pub(super) async fn genesis(&self) {
         let mut storage = self
@@ -7,10 +7,10 @@ use multivm::{
 use zksync_contracts::BaseSystemContracts;
 use zksync_dal::StorageProcessor;
 use zksync_types::{
-    fee_model::BatchFeeInput, Address, L1BatchNumber, L2ChainId, MiniblockNumber,
-    ProtocolVersionId, H256, U256, ZKPORTER_IS_AVAILABLE,
+    fee_model::BatchFeeInput, snapshots::SnapshotRecoveryStatus, Address, L1BatchNumber, L2ChainId,
+    MiniblockNumber, ProtocolVersionId, H256, U256, ZKPORTER_IS_AVAILABLE,
 };
-use zksync_utils::u256_to_h256;
+use zksync_utils::{h256_to_u256, u256_to_h256};
 
 use super::PendingBatchData;
 use crate::state_keeper::extractors;
@@ -31,6 +31,51 @@ pub(crate) fn l1_batch_params(
     virtual_blocks: u32,
     chain_id: L2ChainId,
 ) -> (SystemEnv, L1BatchEnv) {
+    l1_batch_params_with_enforced_base_fee(
+        None,
+        current_l1_batch_number,
+        fee_account,
+        l1_batch_timestamp,
+        previous_batch_hash,
+        fee_input,
+        first_miniblock_number,
+        prev_miniblock_hash,
+        base_system_contracts,
+        validation_computational_gas_limit,
+        protocol_version,
+        virtual_blocks,
+        chain_id,
+    )
+}
+
+/// Same as [`l1_batch_params()`], but allows to fix the base fee used by the VM instead of deriving it
+/// from `fee_input`.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn l1_batch_params_with_enforced_base_fee(
+    enforced_base_fee: Option<u64>,
+    current_l1_batch_number: L1BatchNumber,
+    fee_account: Address,
+    l1_batch_timestamp: u64,
+    previous_batch_hash: U256,
+    fee_input: BatchFeeInput,
+    first_miniblock_number: MiniblockNumber,
+    prev_miniblock_hash: H256,
+    base_system_contracts: BaseSystemContracts,
+    validation_computational_gas_limit: u32,
+    protocol_version: ProtocolVersionId,
+    virtual_blocks: u32,
+    chain_id: L2ChainId,
+) -> (SystemEnv, L1BatchEnv) {
+    debug_assert!(
+        current_l1_batch_number > L1BatchNumber(0),
+        "genesis L1 batch cannot be executed by the state keeper"
+    );
+    debug_assert!(
+        l1_batch_timestamp > 0,
+        "L1 batch timestamp must be positive"
+    );
+    debug_assert!(chain_id.as_u64() > 0, "L2 chain ID must be positive");
+
     (
         SystemEnv {
             zk_porter_available: ZKPORTER_IS_AVAILABLE,
@@ -47,7 +92,7 @@ pub(crate) fn l1_batch_params(
             timestamp: l1_batch_timestamp,
             fee_input,
             fee_account,
-            enforced_base_fee: None,
+            enforced_base_fee,
             first_l2_block: L2BlockEnv {
                 number: first_miniblock_number.0,
                 timestamp: l1_batch_timestamp,
@@ -58,6 +103,35 @@ pub(crate) fn l1_batch_params(
     )
 }
 
+/// Version of [`l1_batch_params()`] for tests that fills all arguments except the batch number with
+/// deterministic defaults, so that tests can focus on the fields they actually care about.
+///
+/// `current_l1_batch_number` must be positive; the genesis L1 batch cannot be executed by the state keeper.
+#[cfg(test)]
+pub(crate) fn l1_batch_params_test_default(
+    current_l1_batch_number: L1BatchNumber,
+) -> (SystemEnv, L1BatchEnv) {
+    use zksync_types::block::MiniblockHasher;
+
+    use crate::state_keeper::tests::BASE_SYSTEM_CONTRACTS;
+
+    let first_miniblock_number = MiniblockNumber(current_l1_batch_number.0);
+    l1_batch_params(
+        current_l1_batch_number,
+        Address::zero(),
+        1,
+        U256::zero(),
+        BatchFeeInput::default(),
+        first_miniblock_number,
+        MiniblockHasher::legacy_hash(first_miniblock_number - 1),
+        BASE_SYSTEM_CONTRACTS.clone(),
+        BLOCK_GAS_LIMIT,
+        ProtocolVersionId::latest(),
+        1,
+        L2ChainId::default(),
+    )
+}
+
 /// Returns the amount of iterations `delay_interval` fits into `max_wait`, rounding up.
 pub(crate) fn poll_iters(delay_interval: Duration, max_wait: Duration) -> usize {
     let max_wait_millis = max_wait.as_millis() as u64;
@@ -67,42 +141,121 @@ pub(crate) fn poll_iters(delay_interval: Duration, max_wait: Duration) -> usize
     ((max_wait_millis + delay_interval_millis - 1) / delay_interval_millis).max(1) as usize
 }
 
+/// Errors that can occur when loading the pending L1 batch from the database.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum LoadPendingBatchError {
+    #[error("previous L1 batch #{0} has no miniblocks")]
+    NoMiniblocksInPrevBatch(L1BatchNumber),
+    #[error("hash of miniblock #{0} preceding the pending miniblock is unavailable")]
+    PrevHashUnavailable(MiniblockNumber),
+    #[error(
+        "miniblock #{expected} following the snapshot is missing, but miniblocks up to #{last_sealed} are present"
+    )]
+    SkippedMiniblocks {
+        expected: MiniblockNumber,
+        last_sealed: MiniblockNumber,
+    },
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl From<zksync_dal::SqlxError> for LoadPendingBatchError {
+    fn from(err: zksync_dal::SqlxError) -> Self {
+        Self::Internal(err.into())
+    }
+}
+
+/// Returns the number of the first miniblock of the pending L1 batch, i.e. the miniblock following the last miniblock
+/// of the previous L1 batch. For the first L1 batch after `snapshot_recovery`, the number is taken from the snapshot
+/// since the snapshot L1 batch and its miniblocks are not necessarily present in the storage.
+pub(crate) async fn pending_miniblock_number(
+    storage: &mut StorageProcessor<'_>,
+    current_l1_batch_number: L1BatchNumber,
+    snapshot_recovery: Option<&SnapshotRecoveryStatus>,
+) -> Result<MiniblockNumber, LoadPendingBatchError> {
+    let prev_l1_batch_number = current_l1_batch_number - 1;
+    if let Some(snapshot_recovery) = snapshot_recovery {
+        if snapshot_recovery.l1_batch_number == prev_l1_batch_number {
+            return Ok(snapshot_recovery.miniblock_number + 1);
+        }
+    }
+
+    let (_, last_miniblock_number_included_in_l1_batch) = storage
+        .blocks_dal()
+        .get_miniblock_range_of_l1_batch(prev_l1_batch_number)
+        .await?
+        .ok_or(LoadPendingBatchError::NoMiniblocksInPrevBatch(
+            prev_l1_batch_number,
+        ))?;
+    Ok(last_miniblock_number_included_in_l1_batch + 1)
+}
+
+/// Loads the parameters of the pending L1 batch from the database. Returns `Ok(None)` if there is no pending
+/// miniblock, i.e., no transactions were executed after the last sealed batch.
 pub(crate) async fn load_l1_batch_params(
     storage: &mut StorageProcessor<'_>,
     current_l1_batch_number: L1BatchNumber,
     fee_account: Address,
     validation_computational_gas_limit: u32,
     chain_id: L2ChainId,
-) -> Option<(SystemEnv, L1BatchEnv)> {
+) -> Result<Option<(SystemEnv, L1BatchEnv)>, LoadPendingBatchError> {
+    let pending_miniblock_number =
+        pending_miniblock_number(storage, current_l1_batch_number, None).await?;
+    load_l1_batch_params_for_miniblock(
+        storage,
+        current_l1_batch_number,
+        pending_miniblock_number,
+        None,
+        fee_account,
+        validation_computational_gas_limit,
+        chain_id,
+    )
+    .await
+}
+
+/// Loads L1 batch params given the number of its first (pending) miniblock. If `snapshot_recovery` is provided,
+/// the previous L1 batch and miniblock are taken from it rather than from the storage.
+async fn load_l1_batch_params_for_miniblock(
+    storage: &mut StorageProcessor<'_>,
+    current_l1_batch_number: L1BatchNumber,
+    pending_miniblock_number: MiniblockNumber,
+    snapshot_recovery: Option<&SnapshotRecoveryStatus>,
+    fee_account: Address,
+    validation_computational_gas_limit: u32,
+    chain_id: L2ChainId,
+) -> Result<Option<(SystemEnv, L1BatchEnv)>, LoadPendingBatchError> {
     // If miniblock doesn't exist (for instance if it's pending), it means that there is no unsynced state (i.e. no transactions
     // were executed after the last sealed batch).
-    let pending_miniblock_number = {
-        let (_, last_miniblock_number_included_in_l1_batch) = storage
-            .blocks_dal()
-            .get_miniblock_range_of_l1_batch(current_l1_batch_number - 1)
-            .await
-            .unwrap()
-            .unwrap();
-        last_miniblock_number_included_in_l1_batch + 1
-    };
-    let pending_miniblock_header = storage
+    let Some(pending_miniblock_header) = storage
         .blocks_dal()
         .get_miniblock_header(pending_miniblock_number)
-        .await
-        .unwrap()?;
+        .await?
+    else {
+        return Ok(None);
+    };
 
-    tracing::info!("Getting previous batch hash");
-    let (previous_l1_batch_hash, _) =
-        extractors::wait_for_prev_l1_batch_params(storage, current_l1_batch_number).await;
+    let (previous_l1_batch_hash, prev_miniblock_hash) = if let Some(recovery) = snapshot_recovery {
+        (
+            h256_to_u256(recovery.l1_batch_root_hash),
+            recovery.miniblock_root_hash,
+        )
+    } else {
+        tracing::info!("Getting previous batch hash");
+        let (previous_l1_batch_hash, _) =
+            extractors::wait_for_prev_l1_batch_params(storage, current_l1_batch_number).await;
 
-    tracing::info!("Getting previous miniblock hash");
-    let prev_miniblock_hash = storage
-        .blocks_dal()
-        .get_miniblock_header(pending_miniblock_number - 1)
-        .await
-        .unwrap()
-        .unwrap()
-        .hash;
+        tracing::info!("Getting previous miniblock hash");
+        let prev_miniblock_number = pending_miniblock_number - 1;
+        let prev_miniblock_hash = storage
+            .blocks_dal()
+            .get_miniblock_header(prev_miniblock_number)
+            .await?
+            .ok_or(LoadPendingBatchError::PrevHashUnavailable(
+                prev_miniblock_number,
+            ))?
+            .hash;
+        (previous_l1_batch_hash, prev_miniblock_hash)
+    };
 
     let base_system_contracts = storage
         .storage_dal()
@@ -117,7 +270,10 @@ pub(crate) async fn load_l1_batch_params(
         .await;
 
     tracing::info!("Previous l1_batch_hash: {}", previous_l1_batch_hash);
-    Some(l1_batch_params(
+    // The enforced base fee is not forwarded here: the state keeper never enforces it when starting a batch
+    // (the bootloader requires it to match the fee derived from `batch_fee_input`), and a pending batch
+    // must be re-executed with the same params it was started with.
+    Ok(Some(l1_batch_params(
         current_l1_batch_number,
         fee_account,
         pending_miniblock_header.timestamp,
@@ -132,37 +288,126 @@ pub(crate) async fn load_l1_batch_params(
             .expect("`protocol_version` must be set for pending miniblock"),
         pending_miniblock_header.virtual_blocks,
         chain_id,
-    ))
+    )))
 }
 
-/// Loads the pending L1 block data from the database.
+/// Loads the pending L1 block data from the database. Returns `Ok(None)` if there is no pending L1 batch.
+///
+/// If the storage was recovered from a snapshot, delegates to [`load_pending_batch_after_snapshot()`].
 pub(crate) async fn load_pending_batch(
     storage: &mut StorageProcessor<'_>,
     current_l1_batch_number: L1BatchNumber,
     fee_account: Address,
     validation_computational_gas_limit: u32,
     chain_id: L2ChainId,
-) -> Option<PendingBatchData> {
-    let (system_env, l1_batch_env) = load_l1_batch_params(
+) -> Result<Option<PendingBatchData>, LoadPendingBatchError> {
+    let snapshot_recovery = storage
+        .snapshot_recovery_dal()
+        .get_applied_snapshot_status()
+        .await?;
+    if let Some(snapshot_recovery) = &snapshot_recovery {
+        return load_pending_batch_after_snapshot(
+            storage,
+            snapshot_recovery,
+            current_l1_batch_number,
+            fee_account,
+            validation_computational_gas_limit,
+            chain_id,
+        )
+        .await;
+    }
+
+    let Some((system_env, l1_batch_env)) = load_l1_batch_params(
         storage,
         current_l1_batch_number,
         fee_account,
         validation_computational_gas_limit,
         chain_id,
     )
-    .await?;
+    .await?
+    else {
+        return Ok(None);
+    };
 
     let pending_miniblocks = storage
         .transactions_dal()
         .get_miniblocks_to_reexecute()
-        .await
-        .unwrap();
+        .await?;
 
-    Some(PendingBatchData {
+    Ok(Some(PendingBatchData {
         l1_batch_env,
         system_env,
         pending_miniblocks,
-    })
+    }))
+}
+
+/// Loads the pending L1 block data from storage initialized from a snapshot rather than from genesis.
+///
+/// For the first L1 batch after the snapshot, the previous L1 batch and miniblock are not (necessarily)
+/// present in the storage, so their numbers and hashes are taken from `snapshot_recovery`. If the miniblock
+/// immediately following the snapshot miniblock is missing, it is checked that no later miniblocks are present.
+pub(crate) async fn load_pending_batch_after_snapshot(
+    storage: &mut StorageProcessor<'_>,
+    snapshot_recovery: &SnapshotRecoveryStatus,
+    current_l1_batch_number: L1BatchNumber,
+    fee_account: Address,
+    validation_computational_gas_limit: u32,
+    chain_id: L2ChainId,
+) -> Result<Option<PendingBatchData>, LoadPendingBatchError> {
+    let is_first_batch_after_snapshot =
+        current_l1_batch_number == snapshot_recovery.l1_batch_number + 1;
+    let params = if is_first_batch_after_snapshot {
+        let pending_miniblock_number =
+            pending_miniblock_number(storage, current_l1_batch_number, Some(snapshot_recovery))
+                .await?;
+        let params = load_l1_batch_params_for_miniblock(
+            storage,
+            current_l1_batch_number,
+            pending_miniblock_number,
+            Some(snapshot_recovery),
+            fee_account,
+            validation_computational_gas_limit,
+            chain_id,
+        )
+        .await?;
+        if params.is_none() {
+            let last_sealed = storage.blocks_dal().get_sealed_miniblock_number().await?;
+            if let Some(last_sealed) =
+                last_sealed.filter(|&number| number >= pending_miniblock_number)
+            {
+                return Err(LoadPendingBatchError::SkippedMiniblocks {
+                    expected: pending_miniblock_number,
+                    last_sealed,
+                });
+            }
+        }
+        params
+    } else {
+        load_l1_batch_params(
+            storage,
+            current_l1_batch_number,
+            fee_account,
+            validation_computational_gas_limit,
+            chain_id,
+        )
+        .await?
+    };
+    let Some((system_env, l1_batch_env)) = params else {
+        return Ok(None);
+    };
+
+    // Pending miniblocks may start later than `l1_batch_env.first_l2_block` since miniblocks without transactions
+    // are not returned.
+    let pending_miniblocks = storage
+        .transactions_dal()
+        .get_miniblocks_to_reexecute()
+        .await?;
+
+    Ok(Some(PendingBatchData {
+        l1_batch_env,
+        system_env,
+        pending_miniblocks,
+    }))
 }
 
 #[cfg(test)]
@@ -178,4 +423,81 @@ mod tests {
         assert_eq!(poll_iters(Duration::from_millis(100), Duration::from_millis(200)), 2);
         assert_eq!(poll_iters(Duration::from_millis(100), Duration::from_millis(201)), 3);
     }
+
+    #[test]
+    fn default_l1_batch_params_for_tests() {
+        let (system_env, l1_batch_env) = l1_batch_params_test_default(L1BatchNumber(3));
+        assert_eq!(system_env.chain_id, L2ChainId::default());
+        assert_eq!(system_env.version, ProtocolVersionId::latest());
+        assert_eq!(l1_batch_env.number, L1BatchNumber(3));
+        assert_eq!(l1_batch_env.first_l2_block.number, 3);
+        assert_eq!(
+            l1_batch_env.first_l2_block.timestamp,
+            l1_batch_env.timestamp
+        );
+        assert_eq!(l1_batch_env.previous_batch_hash, Some(H256::zero()));
+    }
+
+    fn params_with(
+        enforced_base_fee: Option<u64>,
+        number: L1BatchNumber,
+        timestamp: u64,
+        chain_id: L2ChainId,
+    ) -> (SystemEnv, L1BatchEnv) {
+        l1_batch_params_with_enforced_base_fee(
+            enforced_base_fee,
+            number,
+            Address::zero(),
+            timestamp,
+            U256::zero(),
+            BatchFeeInput::default(),
+            MiniblockNumber(number.0),
+            H256::zero(),
+            crate::state_keeper::tests::BASE_SYSTEM_CONTRACTS.clone(),
+            BLOCK_GAS_LIMIT,
+            ProtocolVersionId::latest(),
+            1,
+            chain_id,
+        )
+    }
+
+    #[test]
+    fn l1_batch_params_with_enforced_fee() {
+        let (_, default_env) = l1_batch_params_test_default(L1BatchNumber(1));
+        assert_eq!(default_env.enforced_base_fee, None);
+
+        let (_, l1_batch_env) =
+            params_with(Some(250_000_000), L1BatchNumber(1), 1, L2ChainId::default());
+        assert_eq!(l1_batch_env.enforced_base_fee, Some(250_000_000));
+        assert_eq!(l1_batch_env.fee_input, default_env.fee_input);
+    }
+
+    #[test]
+    fn l1_batch_params_at_valid_boundaries() {
+        let (system_env, l1_batch_env) = params_with(None, L1BatchNumber(1), 1, L2ChainId::from(1));
+        assert_eq!(system_env.chain_id.as_u64(), 1);
+        assert_eq!(l1_batch_env.number, L1BatchNumber(1));
+        assert_eq!(l1_batch_env.timestamp, 1);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "genesis L1 batch")]
+    fn l1_batch_params_for_genesis_batch() {
+        params_with(None, L1BatchNumber(0), 1, L2ChainId::default());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "L1 batch timestamp must be positive")]
+    fn l1_batch_params_with_zero_timestamp() {
+        params_with(None, L1BatchNumber(1), 0, L2ChainId::default());
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "L2 chain ID must be positive")]
+    fn l1_batch_params_with_zero_chain_id() {
+        params_with(None, L1BatchNumber(1), 1, L2ChainId::from(0));
+    }
 }
@@ -5,6 +5,7 @@ use std::{
     time::{Duration, Instant},
 };
 
+use anyhow::Context as _;
 use async_trait::async_trait;
 use multivm::{
     interface::{FinishedL1Batch, L1BatchEnv, SystemEnv},
@@ -85,25 +86,34 @@ impl StateKeeperIO for MempoolIO {
         self.current_miniblock_number
     }
 
-    async fn load_pending_batch(&mut self) -> Option<PendingBatchData> {
+    async fn load_pending_batch(&mut self) -> anyhow::Result<Option<PendingBatchData>> {
         let mut storage = self
             .pool
             .access_storage_tagged("state_keeper")
             .await
-            .unwrap();
+            .context("access_storage_tagged")?;
 
-        let PendingBatchData {
+        let Some(PendingBatchData {
             l1_batch_env,
             system_env,
             pending_miniblocks,
-        } = load_pending_batch(
+        }) = load_pending_batch(
             &mut storage,
             self.current_l1_batch_number,
             self.fee_account,
             self.validation_computational_gas_limit,
             self.chain_id,
         )
-        .await?;
+        .await
+        .with_context(|| {
+            format!(
+                "failed loading pending L1 batch #{}",
+                self.current_l1_batch_number
+            )
+        })?
+        else {
+            return Ok(None);
+        };
         // Initialize the filter for the transactions that come after the pending batch.
         // We use values from the pending block to match the filter with one used before the restart.
         let (base_fee, gas_per_pubdata) =
@@ -114,11 +124,11 @@ impl StateKeeperIO for MempoolIO {
             gas_per_pubdata: gas_per_pubdata as u32,
         };
 
-        Some(PendingBatchData {
+        Ok(Some(PendingBatchData {
             l1_batch_env,
             system_env,
             pending_miniblocks,
-        })
+        }))
     }
 
     async fn wait_for_new_batch_params(
@@ -44,6 +44,65 @@ pub struct PendingBatchData {
     pub(crate) pending_miniblocks: Vec<MiniblockExecutionData>,
 }
 
+#[cfg(test)]
+impl PendingBatchData {
+    pub(crate) fn builder() -> PendingBatchDataBuilder {
+        PendingBatchDataBuilder::default()
+    }
+}
+
+/// Builder for [`PendingBatchData`] used in tests.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct PendingBatchDataBuilder {
+    l1_batch_env: Option<L1BatchEnv>,
+    system_env: Option<SystemEnv>,
+    pending_miniblocks: Vec<MiniblockExecutionData>,
+}
+
+#[cfg(test)]
+impl PendingBatchDataBuilder {
+    pub(crate) fn l1_batch_env(mut self, l1_batch_env: L1BatchEnv) -> Self {
+        self.l1_batch_env = Some(l1_batch_env);
+        self
+    }
+
+    pub(crate) fn system_env(mut self, system_env: SystemEnv) -> Self {
+        self.system_env = Some(system_env);
+        self
+    }
+
+    pub(crate) fn add_miniblock(mut self, miniblock: MiniblockExecutionData) -> Self {
+        self.pending_miniblocks.push(miniblock);
+        self
+    }
+
+    /// Builds the pending batch data, checking that miniblock numbers are sequential and start
+    /// from the first miniblock in the batch.
+    pub(crate) fn build(self) -> anyhow::Result<PendingBatchData> {
+        use anyhow::Context as _;
+
+        let l1_batch_env = self.l1_batch_env.context("`l1_batch_env` is not set")?;
+        let system_env = self.system_env.context("`system_env` is not set")?;
+
+        let mut expected_number = MiniblockNumber(l1_batch_env.first_l2_block.number);
+        for miniblock in &self.pending_miniblocks {
+            anyhow::ensure!(
+                miniblock.number == expected_number,
+                "unexpected pending miniblock number: expected {expected_number}, got {}",
+                miniblock.number
+            );
+            expected_number += 1;
+        }
+
+        Ok(PendingBatchData {
+            l1_batch_env,
+            system_env,
+            pending_miniblocks: self.pending_miniblocks,
+        })
+    }
+}
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct MiniblockParams {
     /// The timestamp of the miniblock
@@ -70,7 +129,7 @@ pub trait StateKeeperIO: 'static + Send + IoSealCriteria {
     fn current_miniblock_number(&self) -> MiniblockNumber;
     /// Returns the data on the batch that was not sealed before the server restart.
     /// See `PendingBatchData` doc-comment for details.
-    async fn load_pending_batch(&mut self) -> Option<PendingBatchData>;
+    async fn load_pending_batch(&mut self) -> anyhow::Result<Option<PendingBatchData>>;
     /// Blocks for up to `max_wait` until the parameters for the next L1 batch are available.
     /// Returns the data required to initialize the VM for the next batch.
     async fn wait_for_new_batch_params(
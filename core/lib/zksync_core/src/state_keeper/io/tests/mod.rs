@@ -3,29 +3,38 @@ use std::time::Duration;
 use futures::FutureExt;
 use multivm::utils::derive_base_fee_and_gas_per_pubdata;
 use zksync_contracts::BaseSystemContractsHashes;
-use zksync_dal::ConnectionPool;
+use zksync_dal::{ConnectionPool, StorageProcessor};
 use zksync_mempool::L2TxFilter;
 use zksync_types::{
-    block::BlockGasCount,
+    block::{BlockGasCount, MiniblockExecutionData, MiniblockHeader},
+    fee::TransactionExecutionMetrics,
     fee_model::{BatchFeeInput, PubdataIndependentBatchFeeModelInput},
-    tx::ExecutionMetrics,
-    AccountTreeId, Address, L1BatchNumber, MiniblockNumber, ProtocolVersionId, StorageKey, VmEvent,
-    H256, U256,
+    snapshots::SnapshotRecoveryStatus,
+    tx::{tx_execution_info::TxExecutionStatus, ExecutionMetrics, TransactionExecutionResult},
+    AccountTreeId, Address, L1BatchNumber, L2ChainId, MiniblockNumber, ProtocolVersionId,
+    StorageKey, VmEvent, H256, U256,
 };
-use zksync_utils::time::seconds_since_epoch;
+use zksync_utils::{be_words_to_bytes, time::seconds_since_epoch};
 
 use self::tester::Tester;
 use crate::{
     state_keeper::{
-        io::{MiniblockParams, MiniblockSealer, StateKeeperIO},
+        io::{
+            common::{load_pending_batch, LoadPendingBatchError},
+            MiniblockParams, MiniblockSealer, PendingBatchData, StateKeeperIO,
+        },
         mempool_actor::l2_tx_filter,
         tests::{
             create_execution_result, create_transaction, create_updates_manager,
-            default_l1_batch_env, default_vm_block_result, Query,
+            default_l1_batch_env, default_system_env, default_vm_block_result, Query,
+            BASE_SYSTEM_CONTRACTS,
         },
         updates::{MiniblockSealCommand, MiniblockUpdates, UpdatesManager},
     },
-    utils::testonly::create_l1_batch_metadata,
+    utils::testonly::{
+        create_l1_batch_metadata, create_l2_transaction, create_miniblock,
+        prepare_empty_recovery_snapshot,
+    },
 };
 
 mod tester;
@@ -77,7 +86,7 @@ async fn test_filter_with_pending_batch() {
     // Before the mempool knows there is a pending batch, the filter is still set to the default values.
     assert_eq!(mempool.filter(), &L2TxFilter::default());
 
-    mempool.load_pending_batch().await;
+    mempool.load_pending_batch().await.unwrap();
     let (want_base_fee, want_gas_per_pubdata) =
         derive_base_fee_and_gas_per_pubdata(fee_input, ProtocolVersionId::latest().into());
     let want_filter = L2TxFilter {
@@ -535,3 +544,249 @@ async fn different_timestamp_for_miniblocks_in_same_batch() {
         .unwrap();
     assert!(next_timestamp > current_timestamp);
 }
+
+#[test]
+fn building_pending_batch_data() {
+    let miniblock = |number| MiniblockExecutionData {
+        number: MiniblockNumber(number),
+        timestamp: number.into(),
+        prev_block_hash: H256::zero(),
+        virtual_blocks: 1,
+        txs: vec![],
+    };
+    let builder = || {
+        PendingBatchData::builder()
+            .l1_batch_env(default_l1_batch_env(1, 1, Address::zero()))
+            .system_env(default_system_env())
+    };
+
+    let pending_batch = builder()
+        .add_miniblock(miniblock(1))
+        .add_miniblock(miniblock(2))
+        .build()
+        .unwrap();
+    assert_eq!(pending_batch.pending_miniblocks.len(), 2);
+
+    builder().add_miniblock(miniblock(2)).build().unwrap_err();
+    builder()
+        .add_miniblock(miniblock(1))
+        .add_miniblock(miniblock(3))
+        .build()
+        .unwrap_err();
+    PendingBatchData::builder()
+        .system_env(default_system_env())
+        .build()
+        .unwrap_err();
+}
+
+#[tokio::test]
+async fn loading_pending_batch_without_previous_miniblocks() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let tester = Tester::new();
+    tester.genesis(&connection_pool).await;
+
+    // L1 batch #1 was never sealed, so there are no miniblocks to derive the pending miniblock number from.
+    let mut storage = connection_pool.access_storage().await.unwrap();
+    let err = load_pending_batch(
+        &mut storage,
+        L1BatchNumber(2),
+        Address::zero(),
+        u32::MAX,
+        L2ChainId::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadPendingBatchError::NoMiniblocksInPrevBatch(L1BatchNumber(1))
+        ),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn loading_pending_batch_after_snapshot_recovery() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut storage = connection_pool.access_storage().await.unwrap();
+    let snapshot_recovery = prepare_empty_recovery_snapshot(&mut storage, 23).await;
+
+    // The L1 batch and miniblock from the snapshot are not present in the storage, so the pending batch
+    // must be resolved using the snapshot recovery status.
+    let pending_batch = load_pending_batch(
+        &mut storage,
+        snapshot_recovery.l1_batch_number + 1,
+        Address::zero(),
+        u32::MAX,
+        L2ChainId::default(),
+    )
+    .await
+    .unwrap();
+    assert!(pending_batch.is_none());
+}
+
+/// Prepares an empty snapshot with distinct L1 batch and miniblock hashes, so that it's possible to check
+/// that they are used as the previous hashes for the first batch after the snapshot.
+async fn prepare_snapshot_with_hashes(
+    storage: &mut StorageProcessor<'_>,
+    l1_batch_number: u32,
+) -> SnapshotRecoveryStatus {
+    let mut snapshot_recovery = prepare_empty_recovery_snapshot(storage, l1_batch_number).await;
+    snapshot_recovery.l1_batch_root_hash = H256::repeat_byte(1);
+    snapshot_recovery.miniblock_root_hash = H256::repeat_byte(2);
+    storage
+        .snapshot_recovery_dal()
+        .set_applied_snapshot_status(&snapshot_recovery)
+        .await
+        .unwrap();
+    snapshot_recovery
+}
+
+/// Inserts a pending miniblock header using the base system contracts from the state keeper tests.
+async fn insert_pending_miniblock(storage: &mut StorageProcessor<'_>, number: MiniblockNumber) {
+    let header = MiniblockHeader {
+        base_system_contracts_hashes: BASE_SYSTEM_CONTRACTS.hashes(),
+        ..create_miniblock(number.0)
+    };
+    storage
+        .blocks_dal()
+        .insert_miniblock(&header)
+        .await
+        .unwrap();
+
+    let factory_deps = [
+        &BASE_SYSTEM_CONTRACTS.bootloader,
+        &BASE_SYSTEM_CONTRACTS.default_aa,
+    ]
+    .into_iter()
+    .map(|contract| (contract.hash, be_words_to_bytes(&contract.code)))
+    .collect();
+    storage
+        .storage_dal()
+        .insert_factory_deps(number, &factory_deps)
+        .await;
+}
+
+#[tokio::test]
+async fn loading_pending_batch_params_from_snapshot() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut storage = connection_pool.access_storage().await.unwrap();
+    let snapshot_recovery = prepare_snapshot_with_hashes(&mut storage, 23).await;
+    // The snapshot miniblock is not present in the storage.
+    let pending_miniblock_number = snapshot_recovery.miniblock_number + 1;
+    insert_pending_miniblock(&mut storage, pending_miniblock_number).await;
+
+    let pending_batch = load_pending_batch(
+        &mut storage,
+        snapshot_recovery.l1_batch_number + 1,
+        Address::zero(),
+        u32::MAX,
+        L2ChainId::default(),
+    )
+    .await
+    .unwrap()
+    .expect("no pending batch");
+
+    let l1_batch_env = &pending_batch.l1_batch_env;
+    assert_eq!(l1_batch_env.number, snapshot_recovery.l1_batch_number + 1);
+    assert_eq!(
+        l1_batch_env.previous_batch_hash,
+        Some(snapshot_recovery.l1_batch_root_hash)
+    );
+    assert_eq!(
+        l1_batch_env.first_l2_block.number,
+        pending_miniblock_number.0
+    );
+    assert_eq!(
+        l1_batch_env.first_l2_block.prev_block_hash,
+        snapshot_recovery.miniblock_root_hash
+    );
+    assert!(pending_batch.pending_miniblocks.is_empty());
+}
+
+#[tokio::test]
+async fn loading_pending_batch_with_empty_first_miniblock_after_snapshot() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut storage = connection_pool.access_storage().await.unwrap();
+    let snapshot_recovery = prepare_snapshot_with_hashes(&mut storage, 23).await;
+    let first_miniblock_number = snapshot_recovery.miniblock_number + 1;
+    insert_pending_miniblock(&mut storage, first_miniblock_number).await;
+
+    // Only the second miniblock after the snapshot has transactions to re-execute.
+    let second_miniblock_number = first_miniblock_number + 1;
+    insert_pending_miniblock(&mut storage, second_miniblock_number).await;
+    let tx = create_l2_transaction(10, 100);
+    storage
+        .transactions_dal()
+        .insert_transaction_l2(tx.clone(), TransactionExecutionMetrics::default())
+        .await;
+    let tx_result = TransactionExecutionResult {
+        hash: tx.hash(),
+        transaction: tx.into(),
+        execution_info: ExecutionMetrics::default(),
+        execution_status: TxExecutionStatus::Success,
+        refunded_gas: 0,
+        operator_suggested_refund: 0,
+        compressed_bytecodes: vec![],
+        call_traces: vec![],
+        revert_reason: None,
+    };
+    storage
+        .transactions_dal()
+        .mark_txs_as_executed_in_miniblock(second_miniblock_number, &[tx_result], 1.into())
+        .await;
+
+    let pending_batch = load_pending_batch(
+        &mut storage,
+        snapshot_recovery.l1_batch_number + 1,
+        Address::zero(),
+        u32::MAX,
+        L2ChainId::default(),
+    )
+    .await
+    .unwrap()
+    .expect("no pending batch");
+
+    assert_eq!(
+        pending_batch.l1_batch_env.first_l2_block.number,
+        first_miniblock_number.0
+    );
+    assert_eq!(
+        pending_batch.l1_batch_env.first_l2_block.prev_block_hash,
+        snapshot_recovery.miniblock_root_hash
+    );
+    let pending_miniblock_numbers: Vec<_> = pending_batch
+        .pending_miniblocks
+        .iter()
+        .map(|miniblock| miniblock.number)
+        .collect();
+    assert_eq!(pending_miniblock_numbers, [second_miniblock_number]);
+}
+
+#[tokio::test]
+async fn loading_pending_batch_with_skipped_miniblocks_after_snapshot() {
+    let connection_pool = ConnectionPool::test_pool().await;
+    let mut storage = connection_pool.access_storage().await.unwrap();
+    let snapshot_recovery = prepare_snapshot_with_hashes(&mut storage, 23).await;
+    let first_miniblock_number = snapshot_recovery.miniblock_number + 1;
+    let skipped_miniblock_number = first_miniblock_number + 1;
+    insert_pending_miniblock(&mut storage, skipped_miniblock_number).await;
+
+    let err = load_pending_batch(
+        &mut storage,
+        snapshot_recovery.l1_batch_number + 1,
+        Address::zero(),
+        u32::MAX,
+        L2ChainId::default(),
+    )
+    .await
+    .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            LoadPendingBatchError::SkippedMiniblocks { expected, last_sealed }
+                if expected == first_miniblock_number && last_sealed == skipped_miniblock_number
+        ),
+        "{err:?}"
+    );
+}
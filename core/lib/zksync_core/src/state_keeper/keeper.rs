@@ -100,7 +100,12 @@ impl ZkSyncStateKeeper {
             mut l1_batch_env,
             mut system_env,
             pending_miniblocks,
-        } = match self.io.load_pending_batch().await {
+        } = match self
+            .io
+            .load_pending_batch()
+            .await
+            .context("load_pending_batch()")?
+        {
             Some(params) => {
                 tracing::info!(
                     "There exists a pending batch consisting of {} miniblocks, the first one is {}",
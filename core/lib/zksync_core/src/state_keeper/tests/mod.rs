@@ -9,21 +9,20 @@ use std::{
 use multivm::{
     interface::{
         CurrentExecutionState, ExecutionResult, FinishedL1Batch, L1BatchEnv, L2BlockEnv, Refunds,
-        SystemEnv, TxExecutionMode, VmExecutionResultAndLogs, VmExecutionStatistics,
+        SystemEnv, VmExecutionResultAndLogs, VmExecutionStatistics,
     },
-    vm_latest::{constants::BLOCK_GAS_LIMIT, VmExecutionLogs},
+    vm_latest::VmExecutionLogs,
 };
 use once_cell::sync::Lazy;
 use zksync_config::configs::chain::StateKeeperConfig;
 use zksync_contracts::{BaseSystemContracts, BaseSystemContractsHashes};
-use zksync_system_constants::ZKPORTER_IS_AVAILABLE;
 use zksync_types::{
     aggregated_operations::AggregatedActionType,
     block::{BlockGasCount, MiniblockExecutionData, MiniblockHasher},
     fee_model::{BatchFeeInput, PubdataIndependentBatchFeeModelInput},
     tx::tx_execution_info::ExecutionMetrics,
-    Address, L1BatchNumber, L2ChainId, LogQuery, MiniblockNumber, ProtocolVersionId,
-    StorageLogQuery, StorageLogQueryType, Timestamp, Transaction, H256, U256,
+    Address, L1BatchNumber, LogQuery, MiniblockNumber, ProtocolVersionId, StorageLogQuery,
+    StorageLogQueryType, Timestamp, Transaction, H256, U256,
 };
 
 mod tester;
@@ -36,6 +35,7 @@ pub(crate) use self::tester::{MockBatchExecutorBuilder, TestBatchExecutorBuilder
 use crate::{
     gas_tracker::l1_batch_base_cost,
     state_keeper::{
+        io::common::l1_batch_params_test_default,
         keeper::POLL_WAIT_DURATION,
         seal_criteria::{
             criteria::{GasCriterion, SlotsCriterion},
@@ -51,15 +51,8 @@ pub(super) static BASE_SYSTEM_CONTRACTS: Lazy<BaseSystemContracts> =
     Lazy::new(BaseSystemContracts::load_from_disk);
 
 pub(super) fn default_system_env() -> SystemEnv {
-    SystemEnv {
-        zk_porter_available: ZKPORTER_IS_AVAILABLE,
-        version: ProtocolVersionId::latest(),
-        base_system_smart_contracts: BASE_SYSTEM_CONTRACTS.clone(),
-        gas_limit: BLOCK_GAS_LIMIT,
-        execution_mode: TxExecutionMode::VerifyExecute,
-        default_validation_computational_gas_limit: BLOCK_GAS_LIMIT,
-        chain_id: L2ChainId::from(270),
-    }
+    let (system_env, _) = l1_batch_params_test_default(L1BatchNumber(1));
+    system_env
 }
 
 pub(super) fn default_l1_batch_env(
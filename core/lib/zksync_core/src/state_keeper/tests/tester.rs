@@ -25,7 +25,10 @@ use crate::{
         batch_executor::{BatchExecutorHandle, Command, L1BatchExecutorBuilder, TxExecutionResult},
         io::{MiniblockParams, PendingBatchData, StateKeeperIO},
         seal_criteria::{IoSealCriteria, SequencerSealer},
-        tests::{default_l1_batch_env, default_vm_block_result, BASE_SYSTEM_CONTRACTS},
+        tests::{
+            default_l1_batch_env, default_system_env, default_vm_block_result,
+            BASE_SYSTEM_CONTRACTS,
+        },
         types::ExecutionMetricsForCriteria,
         updates::UpdatesManager,
         ZkSyncStateKeeper,
@@ -305,19 +308,13 @@ pub(crate) fn bootloader_tip_out_of_gas() -> TxExecutionResult {
 pub(crate) fn pending_batch_data(
     pending_miniblocks: Vec<MiniblockExecutionData>,
 ) -> PendingBatchData {
-    PendingBatchData {
-        l1_batch_env: default_l1_batch_env(1, 1, FEE_ACCOUNT),
-        system_env: SystemEnv {
-            zk_porter_available: false,
-            version: ProtocolVersionId::latest(),
-            base_system_smart_contracts: BASE_SYSTEM_CONTRACTS.clone(),
-            gas_limit: BLOCK_GAS_LIMIT,
-            execution_mode: TxExecutionMode::VerifyExecute,
-            default_validation_computational_gas_limit: BLOCK_GAS_LIMIT,
-            chain_id: L2ChainId::from(270),
-        },
-        pending_miniblocks,
+    let mut builder = PendingBatchData::builder()
+        .l1_batch_env(default_l1_batch_env(1, 1, FEE_ACCOUNT))
+        .system_env(default_system_env());
+    for miniblock in pending_miniblocks {
+        builder = builder.add_miniblock(miniblock);
     }
+    builder.build().expect("invalid pending batch data")
 }
 
 #[allow(clippy::type_complexity, clippy::large_enum_variant)] // It's OK for tests.
@@ -621,8 +618,8 @@ impl StateKeeperIO for TestIO {
         self.miniblock_number
     }
 
-    async fn load_pending_batch(&mut self) -> Option<PendingBatchData> {
-        self.scenario.pending_batch.take()
+    async fn load_pending_batch(&mut self) -> anyhow::Result<Option<PendingBatchData>> {
+        Ok(self.scenario.pending_batch.take())
     }
 
     async fn wait_for_new_batch_params(
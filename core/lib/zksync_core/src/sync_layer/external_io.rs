@@ -1,5 +1,6 @@
 use std::{collections::HashMap, convert::TryInto, iter::FromIterator, time::Duration};
 
+use anyhow::Context as _;
 use async_trait::async_trait;
 use futures::future;
 use multivm::interface::{FinishedL1Batch, L1BatchEnv, SystemEnv};
@@ -22,7 +23,7 @@ use crate::{
     state_keeper::{
         extractors,
         io::{
-            common::{l1_batch_params, load_pending_batch, poll_iters},
+            common::{l1_batch_params, load_pending_batch, pending_miniblock_number, poll_iters},
             MiniblockParams, MiniblockSealerHandle, PendingBatchData, StateKeeperIO,
         },
         metrics::KEEPER_METRICS,
@@ -242,45 +243,75 @@ impl StateKeeperIO for ExternalIO {
         self.current_miniblock_number
     }
 
-    async fn load_pending_batch(&mut self) -> Option<PendingBatchData> {
-        let mut storage = self.pool.access_storage_tagged("sync_layer").await.unwrap();
+    async fn load_pending_batch(&mut self) -> anyhow::Result<Option<PendingBatchData>> {
+        let mut storage = self
+            .pool
+            .access_storage_tagged("sync_layer")
+            .await
+            .context("access_storage_tagged")?;
 
-        // TODO (BFT-99): Do not assume that fee account is the same as in previous batch.
-        let fee_account = storage
-            .blocks_dal()
-            .get_l1_batch_header(self.current_l1_batch_number - 1)
+        let snapshot_recovery = storage
+            .snapshot_recovery_dal()
+            .get_applied_snapshot_status()
             .await
-            .unwrap()
-            .unwrap_or_else(|| {
-                panic!(
-                    "No block header for batch {}",
-                    self.current_l1_batch_number - 1
-                )
-            })
-            .fee_account_address;
-        let pending_miniblock_number = {
-            let (_, last_miniblock_number_included_in_l1_batch) = storage
-                .blocks_dal()
-                .get_miniblock_range_of_l1_batch(self.current_l1_batch_number - 1)
-                .await
-                .unwrap()
-                .unwrap();
-            last_miniblock_number_included_in_l1_batch + 1
-        };
-        let pending_miniblock_header = storage
+            .context("failed getting snapshot recovery status")?;
+        let pending_miniblock_number = pending_miniblock_number(
+            &mut storage,
+            self.current_l1_batch_number,
+            snapshot_recovery.as_ref(),
+        )
+        .await
+        .context("failed getting pending miniblock number")?;
+        let Some(pending_miniblock_header) = storage
             .blocks_dal()
             .get_miniblock_header(pending_miniblock_number)
             .await
-            .unwrap()?;
+            .context("failed getting pending miniblock header")?
+        else {
+            return Ok(None);
+        };
 
-        if pending_miniblock_header.protocol_version.is_none() {
-            // Fetch protocol version ID for pending miniblocks to know which VM to use to re-execute them.
+        let prev_l1_batch_number = self.current_l1_batch_number - 1;
+        let prev_l1_batch_header = storage
+            .blocks_dal()
+            .get_l1_batch_header(prev_l1_batch_number)
+            .await
+            .context("failed getting previous L1 batch header")?;
+        let is_first_batch_after_snapshot = snapshot_recovery.as_ref().map_or(false, |recovery| {
+            recovery.l1_batch_number == prev_l1_batch_number
+        });
+        // The pending miniblock needs to be fetched from the main node if its protocol version is unknown, or
+        // if the previous L1 batch is the snapshot one (and thus may be missing from the storage).
+        let sync_block = if pending_miniblock_header.protocol_version.is_none()
+            || (prev_l1_batch_header.is_none() && is_first_batch_after_snapshot)
+        {
             let sync_block = self
                 .main_node_client
                 .fetch_l2_block(pending_miniblock_header.number, false)
                 .await
-                .expect("Failed to fetch block from the main node")
-                .expect("Block must exist");
+                .context("failed to fetch block from the main node")?
+                .with_context(|| {
+                    format!(
+                        "pending miniblock #{} is missing on the main node",
+                        pending_miniblock_header.number
+                    )
+                })?;
+            Some(sync_block)
+        } else {
+            None
+        };
+
+        // TODO (BFT-99): Do not assume that fee account is the same as in previous batch.
+        let fee_account = match (&prev_l1_batch_header, &sync_block) {
+            (Some(header), _) => header.fee_account_address,
+            (None, Some(sync_block)) if is_first_batch_after_snapshot => {
+                sync_block.operator_address
+            }
+            _ => anyhow::bail!("no header for L1 batch #{prev_l1_batch_number}"),
+        };
+
+        if let (None, Some(sync_block)) = (pending_miniblock_header.protocol_version, &sync_block) {
+            // Fetch protocol version ID for pending miniblocks to know which VM to use to re-execute them.
             // Loading base system contracts will insert protocol version in the database if it's not present there.
             let _ = self
                 .load_base_system_contracts_by_version_id(sync_block.protocol_version)
@@ -289,7 +320,7 @@ impl StateKeeperIO for ExternalIO {
                 .blocks_dal()
                 .set_protocol_version_for_pending_miniblocks(sync_block.protocol_version)
                 .await
-                .unwrap();
+                .context("failed setting protocol version for pending miniblocks")?;
         }
 
         load_pending_batch(
@@ -300,6 +331,12 @@ impl StateKeeperIO for ExternalIO {
             self.chain_id,
         )
         .await
+        .with_context(|| {
+            format!(
+                "failed loading pending L1 batch #{}",
+                self.current_l1_batch_number
+            )
+        })
     }
 
     async fn wait_for_new_batch_params(